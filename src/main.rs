@@ -1,66 +1,133 @@
 use std::sync::Arc;
 
 use wgpu::{
-    Adapter, Backends, Device, DeviceDescriptor, Instance, InstanceDescriptor, Queue,
-    RequestAdapterOptions, Surface, SurfaceConfiguration, SurfaceTargetUnsafe, TextureFormat,
-    TextureUsages,
+    Adapter, Backends, CommandEncoderDescriptor, Device, DeviceDescriptor, Extent3d, Instance,
+    InstanceDescriptor, LoadOp, Operations, Queue, RenderPassColorAttachment,
+    RenderPassDescriptor, RequestAdapterOptions, StoreOp, Surface, SurfaceConfiguration,
+    SurfaceError, Texture, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+    TextureView, TextureViewDescriptor,
 };
 use winit::{
+    application::ApplicationHandler,
     dpi::PhysicalSize,
-    event::Event,
-    event::WindowEvent,
-    event_loop::{ControlFlow, EventLoop},
-    window::{Window, WindowBuilder},
+    event::{ElementState, KeyEvent, WindowEvent},
+    event_loop::{ActiveEventLoop, ControlFlow, EventLoop, EventLoopProxy},
+    keyboard::{KeyCode, PhysicalKey},
+    window::{Window, WindowAttributes, WindowId},
 };
 
+mod renderer;
+mod text;
+
+use renderer::{RenderPass, Renderer};
+use text::TextSpec;
+
+/// Format of [`App::create_depth_texture`]'s depth-stencil target.
+const DEPTH_FORMAT: TextureFormat = TextureFormat::Depth32Float;
+
+/// Knobs for [`App::new`] that differ between native and web targets.
+///
+/// The defaults pick whatever backend, power preference, and limits make
+/// sense for the current `target_arch`; callers only need to build one of
+/// these if they want to override them.
+pub struct AppConfig {
+    pub backends: Backends,
+    pub power_preference: wgpu::PowerPreference,
+    /// Native-only. WebGL2 can't support arbitrary limits, so on wasm32
+    /// `App::new` always requests `Limits::downlevel_webgl2_defaults()`
+    /// with the adapter's max texture dimensions instead of reading this field.
+    pub limits: wgpu::Limits,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        #[cfg(target_arch = "wasm32")]
+        let backends = Backends::GL;
+        #[cfg(not(target_arch = "wasm32"))]
+        let backends = Backends::PRIMARY;
+
+        Self {
+            backends,
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            limits: wgpu::Limits::default(),
+        }
+    }
+}
+
 pub struct App {
     instance: Instance,
-    surface: Surface<'static>,
     surface_format: TextureFormat,
     surface_config: SurfaceConfiguration,
+    present_modes: Vec<wgpu::PresentMode>,
     adapter: Adapter,
     device: Device,
     queue: Queue,
+    renderer: Renderer,
+
+    depth_texture: Texture,
+    depth_view: TextureView,
+
+    font_system: glyphon::FontSystem,
+    swash_cache: glyphon::SwashCache,
+    text_atlas: glyphon::TextAtlas,
+    text_viewport: glyphon::Viewport,
+    text_renderer: glyphon::TextRenderer,
+
+    egui_ctx: egui::Context,
+    egui_state: egui_winit::State,
+    egui_renderer: egui_wgpu::Renderer,
 
-    // Must be declared after surface.
-    window: Window,
     size: PhysicalSize<u32>,
+
+    // `surface` borrows `window` (via `Arc`'s 'static erasure) so it must be
+    // declared first to be dropped first. Both are torn down in `suspended`
+    // and rebuilt in `resumed`, e.g. across an Android lifecycle transition.
+    surface: Option<Surface<'static>>,
+    window: Option<Arc<Window>>,
 }
 
 impl App {
-    pub async fn new(window: Window) -> Self {
+    pub async fn new(window: Arc<Window>, config: AppConfig) -> Self {
         // Get current size
         let size = window.inner_size();
 
         // Instance
         let instance = Instance::new(InstanceDescriptor {
-            backends: Backends::PRIMARY,
+            backends: config.backends,
             ..Default::default()
         });
 
-        // Surface
-        let surface = unsafe {
-            instance.create_surface_unsafe(SurfaceTargetUnsafe::from_window(&window).unwrap())
-        }
-        .unwrap();
+        let surface = Self::create_surface(&instance, window.clone());
 
         // Adapter
         let adapter = instance
             .request_adapter(&RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::HighPerformance,
+                power_preference: config.power_preference,
                 compatible_surface: Some(&surface),
                 force_fallback_adapter: false,
             })
             .await
             .unwrap();
 
+        // WebGL2 only supports a subset of wgpu's limits; the max texture
+        // dimensions are taken from the adapter instead. `config.limits` is
+        // ignored here (see `AppConfig::limits`): a caller-supplied value
+        // could ask for more than WebGL2 can give, which would fail device
+        // creation instead of degrading gracefully.
+        #[cfg(target_arch = "wasm32")]
+        let required_limits =
+            wgpu::Limits::downlevel_webgl2_defaults().using_resolution(adapter.limits());
+        #[cfg(not(target_arch = "wasm32"))]
+        let required_limits = config.limits.clone();
+
         // Device Queue
         let (device, queue) = adapter
             .request_device(
                 &DeviceDescriptor {
                     label: None,
                     required_features: wgpu::Features::empty(),
-                    required_limits: wgpu::Limits::default(),
+                    required_limits,
+                    memory_hints: wgpu::MemoryHints::default(),
                 },
                 None,
             )
@@ -73,88 +140,632 @@ impl App {
             .formats
             .iter()
             .copied()
-            .filter(|f| f.is_srgb())
-            .next()
+            .find(|f| f.is_srgb())
             .unwrap_or(surface_caps.formats[0]);
 
+        let present_modes = surface_caps.present_modes.clone();
+
         let surface_config = SurfaceConfiguration {
             usage: TextureUsages::RENDER_ATTACHMENT,
             format: surface_format,
             width: size.width,
             height: size.height,
-            present_mode: surface_caps.present_modes[0],
+            present_mode: Self::fallback_present_mode(&present_modes, wgpu::PresentMode::Fifo),
             alpha_mode: surface_caps.alpha_modes[0],
             view_formats: vec![],
             desired_maximum_frame_latency: 2,
         };
 
+        let renderer = Renderer::new(surface_config.desired_maximum_frame_latency as usize);
+        println!("Renderer configured for {} frames in flight", renderer.frames_in_flight());
+
+        let (depth_texture, depth_view) = Self::make_depth_texture(&device, size);
+
+        let font_system = glyphon::FontSystem::new();
+        let swash_cache = glyphon::SwashCache::new();
+        let text_cache = glyphon::Cache::new(&device);
+        let text_viewport = glyphon::Viewport::new(&device, &text_cache);
+        let mut text_atlas =
+            glyphon::TextAtlas::new(&device, &queue, &text_cache, surface_format);
+        let text_renderer = glyphon::TextRenderer::new(
+            &mut text_atlas,
+            &device,
+            wgpu::MultisampleState::default(),
+            None,
+        );
+
+        let egui_ctx = egui::Context::default();
+        let egui_state = egui_winit::State::new(
+            egui_ctx.clone(),
+            egui::ViewportId::ROOT,
+            window.as_ref(),
+            Some(window.scale_factor() as f32),
+            None,
+            None,
+        );
+        let egui_renderer = egui_wgpu::Renderer::new(&device, surface_format, None, 1, false);
+
         App {
             instance,
-            surface,
             surface_config,
             surface_format,
+            present_modes,
             adapter,
             device,
             queue,
+            renderer,
+
+            depth_texture,
+            depth_view,
+
+            font_system,
+            swash_cache,
+            text_atlas,
+            text_viewport,
+            text_renderer,
+
+            egui_ctx,
+            egui_state,
+            egui_renderer,
 
-            window,
             size,
+            surface: Some(surface),
+            window: Some(window),
+        }
+    }
+
+    /// Creates the platform-appropriate surface for `window`: a canvas
+    /// target on wasm32, or the native windowing surface elsewhere.
+    fn create_surface(instance: &Instance, window: Arc<Window>) -> Surface<'static> {
+        #[cfg(target_arch = "wasm32")]
+        {
+            use winit::platform::web::WindowExtWebSys;
+            let canvas = window.canvas().expect("window should have a canvas");
+            instance
+                .create_surface(wgpu::SurfaceTarget::Canvas(canvas))
+                .unwrap()
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            instance.create_surface(window).unwrap()
+        }
+    }
+
+    fn make_depth_texture(device: &Device, size: PhysicalSize<u32>) -> (Texture, TextureView) {
+        let texture = device.create_texture(&TextureDescriptor {
+            label: Some("Depth Texture"),
+            size: Extent3d {
+                width: size.width.max(1),
+                height: size.height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: DEPTH_FORMAT,
+            usage: TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&TextureViewDescriptor::default());
+        (texture, view)
+    }
+
+    /// Builds a fresh depth-stencil target sized to the current window,
+    /// ready to attach to a z-testing render pass.
+    pub fn create_depth_texture(&self) -> (Texture, TextureView) {
+        Self::make_depth_texture(&self.device, self.size)
+    }
+
+    /// Builds an offscreen color target in `self.surface_format`, usable both
+    /// as a render attachment and a shader-bound texture (e.g. for rendering
+    /// the scene to an intermediate buffer before a post-process blit to the
+    /// swapchain).
+    pub fn create_render_texture(&self) -> (Texture, TextureView) {
+        let texture = self.device.create_texture(&TextureDescriptor {
+            label: Some("Offscreen Render Texture"),
+            size: Extent3d {
+                width: self.size.width.max(1),
+                height: self.size.height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: self.surface_format,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&TextureViewDescriptor::default());
+        (texture, view)
+    }
+
+    /// Rebuilds the window and surface after a suspend/resume cycle (e.g. an
+    /// Android lifecycle transition), reusing the existing instance, adapter,
+    /// device and queue.
+    pub fn resume(&mut self, window: Arc<Window>) {
+        let size = window.inner_size();
+        let surface = Self::create_surface(&self.instance, window.clone());
+
+        // The recreated native surface isn't guaranteed to support the same
+        // present modes as the one it replaces, so re-query them here.
+        self.present_modes = surface.get_capabilities(&self.adapter).present_modes;
+
+        self.size = size;
+        self.surface_config.width = size.width;
+        self.surface_config.height = size.height;
+        surface.configure(&self.device, &self.surface_config);
+
+        let (depth_texture, depth_view) = Self::make_depth_texture(&self.device, size);
+        self.depth_texture = depth_texture;
+        self.depth_view = depth_view;
+        self.text_viewport.update(
+            &self.queue,
+            glyphon::Resolution {
+                width: size.width,
+                height: size.height,
+            },
+        );
+
+        self.surface = Some(surface);
+        self.window = Some(window);
+    }
+
+    /// Tears down the window and surface ahead of a suspend (e.g. an Android
+    /// lifecycle transition), keeping the instance, adapter, device and
+    /// queue alive for [`App::resume`].
+    pub fn suspend(&mut self) {
+        self.surface = None;
+        self.window = None;
+    }
+
+    /// The live window, if the app isn't currently suspended.
+    pub fn window(&self) -> Option<&Window> {
+        self.window.as_deref()
+    }
+
+    /// Picks `desired` if the surface supports it, otherwise falls back
+    /// through `Mailbox` -> `FifoRelaxed` -> `Fifo`, which every surface
+    /// is guaranteed to support.
+    fn fallback_present_mode(
+        supported: &[wgpu::PresentMode],
+        desired: wgpu::PresentMode,
+    ) -> wgpu::PresentMode {
+        [
+            desired,
+            wgpu::PresentMode::Mailbox,
+            wgpu::PresentMode::FifoRelaxed,
+            wgpu::PresentMode::Fifo,
+        ]
+        .into_iter()
+        .find(|mode| supported.contains(mode))
+        .unwrap_or(wgpu::PresentMode::Fifo)
+    }
+
+    /// Switches the surface's present mode at runtime, reconfiguring
+    /// immediately if the resulting mode differs from the current one.
+    pub fn set_present_mode(&mut self, desired: wgpu::PresentMode) {
+        let chosen = Self::fallback_present_mode(&self.present_modes, desired);
+        if chosen != self.surface_config.present_mode {
+            self.surface_config.present_mode = chosen;
+            if let Some(surface) = &self.surface {
+                surface.configure(&self.device, &self.surface_config);
+            }
         }
     }
 
+    /// Toggles between VSync (`Fifo`) and low-latency (`Mailbox`) presentation.
+    pub fn toggle_present_mode(&mut self) {
+        let desired = if self.surface_config.present_mode == wgpu::PresentMode::Fifo {
+            wgpu::PresentMode::Mailbox
+        } else {
+            wgpu::PresentMode::Fifo
+        };
+        self.set_present_mode(desired);
+    }
+
+    /// Feeds a window event to the egui integration first. Returns `true` if
+    /// egui consumed the event, meaning the caller should skip its own
+    /// handling (e.g. egui has keyboard focus on a text field).
+    pub fn handle_window_event(&mut self, event: &WindowEvent) -> bool {
+        let Some(window) = &self.window else {
+            return false;
+        };
+        self.egui_state.on_window_event(window, event).consumed
+    }
+
+    /// Registers a scene pass with the render graph. Passes run bucketed by
+    /// [`renderer::Phase`], in phase order, once per frame in [`App::render`].
+    pub fn add_pass(&mut self, pass: impl RenderPass + 'static) {
+        self.renderer.add_pass(pass);
+    }
+
+    /// Declares the application's egui UI for this frame, called once per
+    /// frame from [`App::render`]. Its body is where panels, windows, or other
+    /// egui widgets go; as written it draws nothing.
+    pub fn ui(&mut self, _ctx: &egui::Context) {}
+
     pub fn resize(&mut self, new_size: PhysicalSize<u32>) {
         println!("Application Resized to {:?}", new_size);
         if new_size.width > 0 && new_size.height > 0 {
             self.size = new_size;
             self.surface_config.width = new_size.width;
             self.surface_config.height = new_size.height;
-            self.surface.configure(&self.device, &self.surface_config);
+            if let Some(surface) = &self.surface {
+                surface.configure(&self.device, &self.surface_config);
+            }
+            let (depth_texture, depth_view) = Self::make_depth_texture(&self.device, new_size);
+            self.depth_texture = depth_texture;
+            self.depth_view = depth_view;
+            self.text_viewport.update(
+                &self.queue,
+                glyphon::Resolution {
+                    width: new_size.width,
+                    height: new_size.height,
+                },
+            );
+        }
+    }
+
+    /// Shapes `areas` and uploads them to the text atlas, ready to be drawn
+    /// by the overlay pass in the next call to [`App::render`].
+    pub fn draw_text(&mut self, areas: &[TextSpec]) {
+        let mut buffers = Vec::with_capacity(areas.len());
+        for spec in areas {
+            let mut buffer = glyphon::Buffer::new(
+                &mut self.font_system,
+                glyphon::Metrics::new(spec.scale, spec.scale * 1.2),
+            );
+            buffer.set_size(&mut self.font_system, Some(spec.bounds.0), Some(spec.bounds.1));
+            buffer.set_text(
+                &mut self.font_system,
+                spec.content,
+                glyphon::Attrs::new().family(glyphon::Family::SansSerif),
+                glyphon::Shaping::Advanced,
+            );
+            buffer.shape_until_scroll(&mut self.font_system, false);
+            buffers.push(buffer);
+        }
+
+        self.text_viewport.update(
+            &self.queue,
+            glyphon::Resolution {
+                width: self.size.width,
+                height: self.size.height,
+            },
+        );
+
+        let text_areas = areas
+            .iter()
+            .zip(buffers.iter())
+            .map(|(spec, buffer)| glyphon::TextArea {
+                buffer,
+                left: spec.position.0,
+                top: spec.position.1,
+                scale: 1.0,
+                bounds: glyphon::TextBounds {
+                    left: spec.position.0 as i32,
+                    top: spec.position.1 as i32,
+                    right: (spec.position.0 + spec.bounds.0) as i32,
+                    bottom: (spec.position.1 + spec.bounds.1) as i32,
+                },
+                default_color: spec.color,
+                custom_glyphs: &[],
+            });
+
+        self.text_renderer
+            .prepare(
+                &self.device,
+                &self.queue,
+                &mut self.font_system,
+                &mut self.text_atlas,
+                &self.text_viewport,
+                text_areas,
+                &mut self.swash_cache,
+            )
+            .expect("failed to prepare text overlay");
+    }
+
+    pub fn render(&mut self) -> Result<(), SurfaceError> {
+        let Some(surface) = &self.surface else {
+            // Suspended: no window/surface to draw into.
+            return Ok(());
+        };
+        let output = surface.get_current_texture()?;
+        let view = output
+            .texture
+            .create_view(&TextureViewDescriptor::default());
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor {
+                label: Some("Frame Encoder"),
+            });
+
+        self.renderer.record_passes(
+            &self.device,
+            &mut encoder,
+            &view,
+            self.surface_format,
+            Some(&self.depth_view),
+        );
+
+        {
+            let mut overlay_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("Text Overlay Pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Load,
+                        store: StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            self.text_renderer
+                .render(&self.text_atlas, &self.text_viewport, &mut overlay_pass)
+                .expect("failed to render text overlay");
+        }
+
+        let window = self
+            .window
+            .clone()
+            .expect("window should exist while surface is live");
+
+        let egui_ctx = self.egui_ctx.clone();
+        let raw_input = self.egui_state.take_egui_input(&window);
+        let full_output = egui_ctx.run(raw_input, |ctx| self.ui(ctx));
+        self.egui_state
+            .handle_platform_output(&window, full_output.platform_output);
+
+        let clipped_primitives =
+            egui_ctx.tessellate(full_output.shapes, full_output.pixels_per_point);
+
+        for (id, image_delta) in &full_output.textures_delta.set {
+            self.egui_renderer
+                .update_texture(&self.device, &self.queue, *id, image_delta);
+        }
+
+        let screen_descriptor = egui_wgpu::ScreenDescriptor {
+            size_in_pixels: [self.surface_config.width, self.surface_config.height],
+            pixels_per_point: full_output.pixels_per_point,
+        };
+        self.egui_renderer.update_buffers(
+            &self.device,
+            &self.queue,
+            &mut encoder,
+            &clipped_primitives,
+            &screen_descriptor,
+        );
+
+        {
+            let egui_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("Egui Pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Load,
+                        store: StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            // egui-wgpu's Renderer::render takes a `'static` pass; forget_lifetime
+            // detaches it from `encoder`'s borrow, which we don't need back until
+            // after this block ends anyway.
+            let mut egui_pass = egui_pass.forget_lifetime();
+            self.egui_renderer
+                .render(&mut egui_pass, &clipped_primitives, &screen_descriptor);
+        }
+
+        for id in &full_output.textures_delta.free {
+            self.egui_renderer.free_texture(id);
         }
+
+        self.queue.submit(std::iter::once(encoder.finish()));
+        output.present();
+
+        self.renderer.advance_frame();
+
+        Ok(())
     }
 }
 
-fn main() {
-    let event_loop = EventLoop::new().unwrap();
-    let window = WindowBuilder::new()
+fn window_attributes() -> WindowAttributes {
+    Window::default_attributes()
         .with_decorations(true)
         .with_resizable(true)
         .with_transparent(false)
         .with_title("Celestial")
-        .build(&event_loop)
-        .unwrap();
+}
+
+#[cfg(target_arch = "wasm32")]
+fn mount_canvas(window: &Window) {
+    use winit::platform::web::WindowExtWebSys;
+    web_sys::window()
+        .and_then(|win| win.document())
+        .and_then(|doc| {
+            let canvas = web_sys::Element::from(window.canvas()?);
+            doc.body()?.append_child(&canvas).ok()
+        })
+        .expect("couldn't append canvas to document body");
+}
+
+/// Sent once the async `App::new` finishes, so wasm32 (which can't block on
+/// a future inside `resumed`) can hand the finished `App` back to the loop.
+#[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+enum UserEvent {
+    AppReady(App),
+}
+
+struct AppHandler {
+    #[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+    proxy: EventLoopProxy<UserEvent>,
+    app: Option<App>,
+}
+
+impl AppHandler {
+    fn create_window(event_loop: &ActiveEventLoop) -> Arc<Window> {
+        let window = Arc::new(event_loop.create_window(window_attributes()).unwrap());
+        #[cfg(target_arch = "wasm32")]
+        mount_canvas(&window);
+        window
+    }
+}
+
+impl ApplicationHandler<UserEvent> for AppHandler {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        // Already running: this is a resume after `suspended` dropped the
+        // window/surface (e.g. an Android lifecycle transition). Reuse the
+        // existing instance/adapter/device/queue.
+        if let Some(app) = &mut self.app {
+            let window = Self::create_window(event_loop);
+            app.resume(window.clone());
+            window.request_redraw();
+            return;
+        }
+
+        let window = Self::create_window(event_loop);
 
-    let window_id = window.id();
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let app = pollster::block_on(App::new(window.clone(), AppConfig::default()));
+            window.request_redraw();
+            self.app = Some(app);
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            let proxy = self.proxy.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let app = App::new(window.clone(), AppConfig::default()).await;
+                window.request_redraw();
+                let _ = proxy.send_event(UserEvent::AppReady(app));
+            });
+        }
+    }
+
+    fn suspended(&mut self, _event_loop: &ActiveEventLoop) {
+        if let Some(app) = &mut self.app {
+            app.suspend();
+        }
+    }
 
-    let mut app: App = pollster::block_on(App::new(window));
+    fn user_event(&mut self, _event_loop: &ActiveEventLoop, event: UserEvent) {
+        let UserEvent::AppReady(app) = event;
+        self.app = Some(app);
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        _window_id: WindowId,
+        event: WindowEvent,
+    ) {
+        let Some(app) = &mut self.app else {
+            return;
+        };
 
-    event_loop.set_control_flow(ControlFlow::Wait);
-    event_loop
-        .run(move |event, target| match event {
-            Event::WindowEvent {
-                event,
-                window_id: id,
+        if app.handle_window_event(&event) {
+            return;
+        }
+
+        match event {
+            WindowEvent::CloseRequested => {
+                println!("Close Requested");
+                event_loop.exit();
+            }
+            WindowEvent::Resized(physical_size) => {
+                app.resize(physical_size);
+            }
+            WindowEvent::ScaleFactorChanged {
+                mut inner_size_writer,
+                ..
             } => {
-                if id == window_id {
-                    match event {
-                        WindowEvent::CloseRequested => {
-                            println!("Close Requested");
-                            target.exit();
-                        }
-                        WindowEvent::Resized(physical_size) => {
-                            app.resize(physical_size);
-                        }
-                        WindowEvent::ScaleFactorChanged {
-                            mut inner_size_writer,
-                            ..
-                        } => {
-                            inner_size_writer.request_inner_size(app.size).unwrap();
-                            app.resize(app.size);
-                        }
-                        _ => (),
+                inner_size_writer.request_inner_size(app.size).unwrap();
+                app.resize(app.size);
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key: PhysicalKey::Code(KeyCode::KeyV),
+                        state: ElementState::Pressed,
+                        repeat: false,
+                        ..
+                    },
+                ..
+            } => {
+                app.toggle_present_mode();
+            }
+            WindowEvent::RedrawRequested => {
+                match app.render() {
+                    Ok(()) => (),
+                    Err(SurfaceError::Lost | SurfaceError::Outdated) => app.resize(app.size),
+                    Err(SurfaceError::OutOfMemory) => {
+                        println!("Out of memory, exiting");
+                        event_loop.exit();
                     }
+                    Err(e) => println!("Surface error: {:?}", e),
+                }
+                if let Some(window) = app.window() {
+                    window.request_redraw();
                 }
             }
             _ => (),
-        })
-        .unwrap();
+        }
+    }
+}
+
+fn main() {
+    #[cfg(target_arch = "wasm32")]
+    console_error_panic_hook::set_once();
+
+    let event_loop = EventLoop::<UserEvent>::with_user_event().build().unwrap();
+    event_loop.set_control_flow(ControlFlow::Poll);
+
+    let mut handler = AppHandler {
+        proxy: event_loop.create_proxy(),
+        app: None,
+    };
+
+    #[cfg(not(target_arch = "wasm32"))]
+    event_loop.run_app(&mut handler).unwrap();
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        use winit::platform::web::EventLoopExtWebSys;
+        event_loop.spawn_app(handler);
+    }
+}
+
+#[cfg(test)]
+mod present_mode_tests {
+    use super::*;
+
+    #[test]
+    fn prefers_desired_mode_when_supported() {
+        let supported = [wgpu::PresentMode::Fifo, wgpu::PresentMode::Mailbox];
+        let chosen = App::fallback_present_mode(&supported, wgpu::PresentMode::Mailbox);
+        assert_eq!(chosen, wgpu::PresentMode::Mailbox);
+    }
+
+    #[test]
+    fn falls_back_through_the_priority_list() {
+        let supported = [wgpu::PresentMode::FifoRelaxed, wgpu::PresentMode::Fifo];
+        let chosen = App::fallback_present_mode(&supported, wgpu::PresentMode::Mailbox);
+        assert_eq!(chosen, wgpu::PresentMode::FifoRelaxed);
+    }
+
+    #[test]
+    fn defaults_to_fifo_when_nothing_in_the_priority_list_is_supported() {
+        let supported = [wgpu::PresentMode::Immediate];
+        let chosen = App::fallback_present_mode(&supported, wgpu::PresentMode::Mailbox);
+        assert_eq!(chosen, wgpu::PresentMode::Fifo);
+    }
 }
@@ -0,0 +1,227 @@
+use wgpu::{
+    Color, CommandEncoder, Device, LoadOp, Operations, RenderPassColorAttachment,
+    RenderPassDepthStencilAttachment, RenderPassDescriptor, TextureFormat, TextureView,
+};
+
+const CLEAR_COLOR: Color = Color {
+    r: 0.01,
+    g: 0.01,
+    b: 0.02,
+    a: 1.0,
+};
+
+/// Coarse ordering bucket a [`RenderPass`] is drawn in within a frame.
+///
+/// Phases always run in declaration order below; the first phase to run
+/// clears the target, every later phase loads what came before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Phase {
+    Opaque,
+    Transparent,
+    Overlay,
+}
+
+const PHASE_ORDER: [Phase; 3] = [Phase::Opaque, Phase::Transparent, Phase::Overlay];
+
+/// Shared state handed to a [`RenderPass`] while it records its work for the frame.
+pub struct PassContext<'a> {
+    pub device: &'a Device,
+    pub view: &'a TextureView,
+    pub format: TextureFormat,
+    /// The shared depth-stencil target, for phases that z-test (`Opaque`, `Transparent`).
+    pub depth_view: Option<&'a TextureView>,
+    /// `frame_index % frames_in_flight`, for indexing per-frame resources.
+    pub frame_slot: usize,
+}
+
+/// A single stage of the render graph, grouped into a [`Phase`] by the renderer.
+pub trait RenderPass {
+    fn phase(&self) -> Phase;
+    fn record<'pass>(&'pass self, ctx: &PassContext, render_pass: &mut wgpu::RenderPass<'pass>);
+}
+
+/// Owns an ordered set of [`RenderPass`]es and drives them once per frame.
+///
+/// Passes are bucketed by [`Phase`] and run in a fixed order, sharing a single
+/// command encoder. `frames_in_flight` is the modulus of [`Renderer::frame_slot`],
+/// which passes receive as [`PassContext::frame_slot`] to index their own
+/// per-frame resources (uniform staging, ...). The renderer itself holds no
+/// per-frame resources and does not limit how many frames are in flight; the
+/// command encoder is created afresh by the caller for every frame.
+pub struct Renderer {
+    passes: Vec<Box<dyn RenderPass>>,
+    frames_in_flight: usize,
+    frame_index: usize,
+}
+
+impl Renderer {
+    pub fn new(frames_in_flight: usize) -> Self {
+        Self {
+            passes: Vec::new(),
+            frames_in_flight,
+            frame_index: 0,
+        }
+    }
+
+    pub fn frames_in_flight(&self) -> usize {
+        self.frames_in_flight
+    }
+
+    pub fn add_pass(&mut self, pass: impl RenderPass + 'static) {
+        self.passes.push(Box::new(pass));
+    }
+
+    /// `frame_index % frames_in_flight` for the frame about to be recorded.
+    pub fn frame_slot(&self) -> usize {
+        self.frame_index % self.frames_in_flight
+    }
+
+    /// Advances to the next frame, wrapping `frame_index` as needed.
+    pub fn advance_frame(&mut self) {
+        self.frame_index = self.frame_index.wrapping_add(1);
+    }
+
+    /// Buckets registered passes by [`Phase`] and records each phase as one
+    /// `wgpu::RenderPass` into `encoder`, targeting `view`. `depth_view`, if
+    /// given, is bound to the z-testing phases (`Opaque`, `Transparent`) and
+    /// cleared once on the first of them; `Overlay` never gets a depth
+    /// attachment, since it draws 2D UI on top of the finished scene.
+    /// Callers that need to record further passes into the same frame (e.g.
+    /// a UI overlay) can do so after this returns, reusing `encoder` and `view`.
+    pub fn record_passes(
+        &self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        view: &TextureView,
+        format: TextureFormat,
+        depth_view: Option<&TextureView>,
+    ) {
+        let frame_slot = self.frame_slot();
+        let buckets = bucket_by_phase(&self.passes);
+
+        let ctx = PassContext {
+            device,
+            view,
+            format,
+            depth_view,
+            frame_slot,
+        };
+
+        let mut depth_cleared = false;
+        for (phase_index, indices) in buckets.iter().enumerate() {
+            let load = if phase_index == 0 {
+                LoadOp::Clear(CLEAR_COLOR)
+            } else {
+                LoadOp::Load
+            };
+
+            let phase_depth = (PHASE_ORDER[phase_index] != Phase::Overlay)
+                .then_some(depth_view)
+                .flatten();
+
+            let depth_stencil_attachment = phase_depth.map(|depth_view| {
+                let depth_load = if depth_cleared {
+                    LoadOp::Load
+                } else {
+                    LoadOp::Clear(1.0)
+                };
+                depth_cleared = true;
+
+                RenderPassDepthStencilAttachment {
+                    view: depth_view,
+                    depth_ops: Some(Operations {
+                        load: depth_load,
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }
+            });
+
+            let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("Phase Pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: Operations {
+                        load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            for &index in indices {
+                self.passes[index].record(&ctx, &mut render_pass);
+            }
+        }
+    }
+}
+
+/// Groups `passes` by [`PHASE_ORDER`] index, preserving each phase's
+/// relative pass order. A phase with no registered passes gets an empty bucket.
+fn bucket_by_phase(passes: &[Box<dyn RenderPass>]) -> Vec<Vec<usize>> {
+    let mut buckets: Vec<Vec<usize>> = vec![Vec::new(); PHASE_ORDER.len()];
+    for (index, pass) in passes.iter().enumerate() {
+        let phase_index = PHASE_ORDER
+            .iter()
+            .position(|phase| *phase == pass.phase())
+            .expect("Phase must be one of PHASE_ORDER");
+        buckets[phase_index].push(index);
+    }
+    buckets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct StubPass(Phase);
+
+    impl RenderPass for StubPass {
+        fn phase(&self) -> Phase {
+            self.0
+        }
+
+        fn record<'pass>(&'pass self, _ctx: &PassContext, _render_pass: &mut wgpu::RenderPass<'pass>) {}
+    }
+
+    fn stub_passes(phases: &[Phase]) -> Vec<Box<dyn RenderPass>> {
+        phases
+            .iter()
+            .map(|&phase| Box::new(StubPass(phase)) as Box<dyn RenderPass>)
+            .collect()
+    }
+
+    #[test]
+    fn empty_passes_yield_empty_buckets() {
+        let buckets = bucket_by_phase(&stub_passes(&[]));
+        assert_eq!(buckets, vec![Vec::<usize>::new(); PHASE_ORDER.len()]);
+    }
+
+    #[test]
+    fn passes_are_grouped_by_phase_in_registration_order() {
+        let passes = stub_passes(&[
+            Phase::Transparent,
+            Phase::Opaque,
+            Phase::Overlay,
+            Phase::Opaque,
+        ]);
+        let buckets = bucket_by_phase(&passes);
+
+        assert_eq!(buckets[0], vec![1, 3]); // Opaque
+        assert_eq!(buckets[1], vec![0]); // Transparent
+        assert_eq!(buckets[2], vec![2]); // Overlay
+    }
+
+    #[test]
+    fn a_phase_with_no_passes_gets_an_empty_bucket() {
+        let passes = stub_passes(&[Phase::Opaque, Phase::Opaque]);
+        let buckets = bucket_by_phase(&passes);
+
+        assert_eq!(buckets[0], vec![0, 1]);
+        assert!(buckets[1].is_empty());
+        assert!(buckets[2].is_empty());
+    }
+}
@@ -0,0 +1,8 @@
+/// One piece of text to draw over the scene, via [`crate::App::draw_text`].
+pub struct TextSpec<'a> {
+    pub content: &'a str,
+    pub position: (f32, f32),
+    pub bounds: (f32, f32),
+    pub scale: f32,
+    pub color: glyphon::Color,
+}